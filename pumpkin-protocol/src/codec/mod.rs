@@ -0,0 +1,3 @@
+pub mod identifier;
+pub mod var_int;
+pub mod var_short;
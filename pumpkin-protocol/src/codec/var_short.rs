@@ -0,0 +1,62 @@
+use std::io::{Read, Write};
+
+use crate::bytebuf::ReadingError;
+use crate::packet_macro::PacketField;
+
+/// Forge's `VarShort` length prefix, used by large custom-payload packets
+/// whose size overflows a plain `i16`.
+///
+/// It encodes an unsigned value up to `0x1FFFFF` (21 bits): the low 15 bits
+/// are written as a big-endian `u16`; when the value exceeds `0x7FFF` the high
+/// bit (`0x8000`) of that `u16` is set and the remaining upper 7 bits follow as
+/// a single `u8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VarShort(pub u32);
+
+impl VarShort {
+    /// The largest value a `VarShort` can represent.
+    pub const MAX: u32 = 0x1FFFFF;
+
+    pub fn written_size(&self) -> usize {
+        if self.0 > 0x7FFF { 3 } else { 2 }
+    }
+}
+
+impl From<u32> for VarShort {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<VarShort> for u32 {
+    fn from(value: VarShort) -> Self {
+        value.0
+    }
+}
+
+impl PacketField for VarShort {
+    fn write_field(&self, write: &mut impl Write) {
+        let low = (self.0 & 0x7FFF) as u16;
+        if self.0 > 0x7FFF {
+            write
+                .write_all(&(low | 0x8000).to_be_bytes())
+                .expect("writing a VarShort is infallible");
+            write
+                .write_all(&[(self.0 >> 15) as u8])
+                .expect("writing a VarShort is infallible");
+        } else {
+            write
+                .write_all(&low.to_be_bytes())
+                .expect("writing a VarShort is infallible");
+        }
+    }
+
+    fn read_field(read: &mut impl Read) -> Result<Self, ReadingError> {
+        let first = u16::read_field(read)?;
+        let mut value = (first & 0x7FFF) as u32;
+        if first & 0x8000 != 0 {
+            value |= (u8::read_field(read)? as u32) << 15;
+        }
+        Ok(VarShort(value))
+    }
+}
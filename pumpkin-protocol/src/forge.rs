@@ -0,0 +1,275 @@
+//! Forge (`FML|HS`) login handshake and plugin-channel support.
+//!
+//! Modded clients negotiate their mod set over the legacy
+//! `minecraft:register` / `FML|HS` plugin-message channels during
+//! [`ConnectionState::Login`](crate::ConnectionState::Login) and
+//! [`ConnectionState::Config`](crate::ConnectionState::Config). This module
+//! carries the custom-payload packet pair, the small handshake state machine
+//! and the [`ModList`] type advertised in the status ping.
+
+use std::io::{Read, Write};
+
+use bytes::Bytes;
+use serde::Serialize;
+
+use crate::bytebuf::ReadingError;
+use crate::bytebuf::packet::Packet;
+use crate::codec::identifier::Identifier;
+use crate::codec::var_short::VarShort;
+use crate::packet_macro::PacketField;
+use crate::{ClientPacket, ServerPacket};
+
+/// The plugin channel Forge drives its login handshake over.
+pub const FML_HANDSHAKE_CHANNEL: &str = "FML|HS";
+/// The channel clients and servers register their plugin channels on.
+pub const REGISTER_CHANNEL: &str = "minecraft:register";
+
+/// A custom-payload packet: a plugin channel plus its opaque body.
+///
+/// Both directions use the same wire shape, so a single type backs the
+/// clientbound and serverbound halves of the exchange. The body is prefixed
+/// with a [`VarShort`] length so oversized registry/modlist payloads survive
+/// the round-trip without overflowing a plain `i16`.
+pub struct CustomPayload {
+    pub channel: Identifier,
+    pub data: Bytes,
+}
+
+impl CustomPayload {
+    pub fn new(channel: Identifier, data: Bytes) -> Self {
+        Self { channel, data }
+    }
+}
+
+impl Packet for CustomPayload {
+    // Clientbound/serverbound custom payload during Login/Config.
+    const PACKET_ID: i32 = 0x01;
+}
+
+impl ClientPacket for CustomPayload {
+    fn write(&self, _protocol_version: u16, write: &mut impl Write) {
+        self.channel.write_field(write);
+        VarShort(self.data.len() as u32).write_field(write);
+        write
+            .write_all(&self.data)
+            .expect("writing custom-payload body is infallible");
+    }
+}
+
+impl ServerPacket for CustomPayload {
+    fn read(_protocol_version: u16, read: &mut impl Read) -> Result<Self, ReadingError> {
+        let channel = Identifier::read_field(read)?;
+        // A `VarShort` caps the body at `VarShort::MAX` (< `MAX_PACKET_SIZE`),
+        // so the length prefix is inherently bounded and cannot request a
+        // gigabyte buffer — no separate size check is needed here.
+        let len = VarShort::read_field(read)?.0 as usize;
+        let mut data = vec![0u8; len];
+        read.read_exact(&mut data)
+            .map_err(|e| ReadingError::Incomplete(e.to_string()))?;
+        Ok(Self { channel, data: Bytes::from(data) })
+    }
+}
+
+/// A single entry of a Forge [`ModList`].
+pub struct Mod {
+    pub name: String,
+    pub version: String,
+}
+
+/// The list of mods a peer advertises, serialized as a `VarShort`-prefixed
+/// sequence of [`Mod`] entries.
+pub struct ModList {
+    pub mods: Vec<Mod>,
+}
+
+impl ModList {
+    pub fn new(mods: Vec<Mod>) -> Self {
+        Self { mods }
+    }
+
+    /// The number of mods in the list, as advertised in the status ping.
+    pub fn count(&self) -> usize {
+        self.mods.len()
+    }
+
+    pub fn write(&self, write: &mut impl Write) {
+        VarShort(self.mods.len() as u32).write_field(write);
+        for entry in &self.mods {
+            entry.name.write_field(write);
+            entry.version.write_field(write);
+        }
+    }
+
+    pub fn read(read: &mut impl Read) -> Result<Self, ReadingError> {
+        let len = VarShort::read_field(read)?.0 as usize;
+        // `len` is an entry count bounded by `VarShort::MAX`; grow the Vec as
+        // entries actually decode rather than pre-reserving from the untrusted
+        // count, so a hostile prefix can't force a large up-front allocation
+        // (the stream EOFs long before).
+        let mut mods = Vec::new();
+        for _ in 0..len {
+            let name = String::read_field(read)?;
+            let version = String::read_field(read)?;
+            mods.push(Mod { name, version });
+        }
+        Ok(Self { mods })
+    }
+}
+
+/// The Forge mod information advertised in the status ping's `modinfo`
+/// object, as consumed by the vanilla-Forge server list.
+#[derive(Serialize)]
+pub struct ForgeStatus {
+    /// Always `"FML"` for a legacy Forge handshake.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The advertised mods, as `{ modid, version }` pairs.
+    #[serde(rename = "modList")]
+    pub mod_list: Vec<ForgeModInfo>,
+}
+
+#[derive(Serialize)]
+pub struct ForgeModInfo {
+    pub modid: String,
+    pub version: String,
+}
+
+impl ForgeStatus {
+    /// Builds a status payload from a [`ModList`].
+    pub fn from_mod_list(mods: &ModList) -> Self {
+        Self {
+            kind: "FML".to_string(),
+            mod_list: mods
+                .mods
+                .iter()
+                .map(|m| ForgeModInfo { modid: m.name.clone(), version: m.version.clone() })
+                .collect(),
+        }
+    }
+}
+
+/// The `FML|HS` sub-packets, discriminated by their leading byte.
+pub enum FmlHandshake {
+    ServerHello { fml_protocol: u8, override_dimension: Option<i32> },
+    ClientHello { fml_protocol: u8 },
+    ModList(ModList),
+    RegistryData,
+    HandshakeAck { phase: u8 },
+}
+
+impl FmlHandshake {
+    const SERVER_HELLO: u8 = 0;
+    const CLIENT_HELLO: u8 = 1;
+    const MOD_LIST: u8 = 2;
+    const REGISTRY_DATA: u8 = 3;
+    const HANDSHAKE_ACK: u8 = 255;
+
+    pub fn read(read: &mut impl Read) -> Result<Self, ReadingError> {
+        let discriminator = u8::read_field(read)?;
+        match discriminator {
+            Self::SERVER_HELLO => {
+                let fml_protocol = u8::read_field(read)?;
+                // The override dimension is only present for FML protocol >= 1.
+                let override_dimension =
+                    if fml_protocol >= 1 { Some(i32::read_field(read)?) } else { None };
+                Ok(Self::ServerHello { fml_protocol, override_dimension })
+            }
+            Self::CLIENT_HELLO => Ok(Self::ClientHello { fml_protocol: u8::read_field(read)? }),
+            Self::MOD_LIST => Ok(Self::ModList(ModList::read(read)?)),
+            Self::REGISTRY_DATA => Ok(Self::RegistryData),
+            Self::HANDSHAKE_ACK => Ok(Self::HandshakeAck { phase: u8::read_field(read)? }),
+            other => Err(ReadingError::Message(format!(
+                "unknown FML|HS sub-packet {other}"
+            ))),
+        }
+    }
+
+    pub fn write(&self, write: &mut impl Write) {
+        match self {
+            Self::ServerHello { fml_protocol, override_dimension } => {
+                Self::SERVER_HELLO.write_field(write);
+                fml_protocol.write_field(write);
+                // Mirrors `read`: the dimension is present for protocol >= 1,
+                // defaulting to 0 when none is set.
+                if *fml_protocol >= 1 {
+                    override_dimension.unwrap_or(0).write_field(write);
+                }
+            }
+            Self::ClientHello { fml_protocol } => {
+                Self::CLIENT_HELLO.write_field(write);
+                fml_protocol.write_field(write);
+            }
+            Self::ModList(mods) => {
+                Self::MOD_LIST.write_field(write);
+                mods.write(write);
+            }
+            Self::RegistryData => Self::REGISTRY_DATA.write_field(write),
+            Self::HandshakeAck { phase } => {
+                Self::HANDSHAKE_ACK.write_field(write);
+                phase.write_field(write);
+            }
+        }
+    }
+}
+
+/// Where the server-side driver is in the `FML|HS` exchange.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum HandshakePhase {
+    /// Before the opening `ServerHello` has been emitted.
+    Start,
+    /// `ServerHello` sent; awaiting the client's `ClientHello`.
+    WaitingClientHello,
+    /// `ClientHello` seen; awaiting the client's `ModList`.
+    WaitingModList,
+    /// `RegistryData` sent; awaiting the final `HandshakeAck`.
+    WaitingAck,
+    Complete,
+}
+
+/// Drives the **server** side of the `FML|HS` handshake: it emits the opening
+/// `ServerHello`, consumes the client's `ClientHello`/`ModList`, replies with
+/// `RegistryData` and completes on the final `HandshakeAck`.
+pub struct FmlHandshakeState {
+    pub phase: HandshakePhase,
+    pub client_mods: Option<ModList>,
+}
+
+impl Default for FmlHandshakeState {
+    fn default() -> Self {
+        Self { phase: HandshakePhase::Start, client_mods: None }
+    }
+}
+
+impl FmlHandshakeState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Emits the opening `ServerHello` and advances past [`HandshakePhase::Start`].
+    pub fn start(&mut self) -> FmlHandshake {
+        self.phase = HandshakePhase::WaitingClientHello;
+        FmlHandshake::ServerHello { fml_protocol: 2, override_dimension: None }
+    }
+
+    /// Feeds one decoded client sub-packet into the machine, returning the
+    /// reply to send (if any) and advancing [`Self::phase`].
+    pub fn handle(&mut self, packet: FmlHandshake) -> Option<FmlHandshake> {
+        match packet {
+            FmlHandshake::ClientHello { .. } => {
+                self.phase = HandshakePhase::WaitingModList;
+                None
+            }
+            FmlHandshake::ModList(mods) => {
+                self.client_mods = Some(mods);
+                self.phase = HandshakePhase::WaitingAck;
+                Some(FmlHandshake::RegistryData)
+            }
+            FmlHandshake::HandshakeAck { .. } => {
+                self.phase = HandshakePhase::Complete;
+                Some(FmlHandshake::HandshakeAck { phase: 3 })
+            }
+            // A well-behaved client never sends these to the server.
+            FmlHandshake::ServerHello { .. } | FmlHandshake::RegistryData => None,
+        }
+    }
+}
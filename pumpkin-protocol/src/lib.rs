@@ -1,7 +1,8 @@
+use std::io::{Read, Write};
 use std::num::NonZeroU16;
 
 use bytebuf::{ByteBufMut, ReadingError, packet::Packet};
-use bytes::{Buf, BufMut, Bytes};
+use bytes::Bytes;
 use codec::{identifier::Identifier, var_int::VarInt};
 use pumpkin_util::text::{TextComponent, style::Style};
 use serde::{Deserialize, Serialize, Serializer};
@@ -10,6 +11,10 @@ pub mod bytebuf;
 #[cfg(feature = "clientbound")]
 pub mod client;
 pub mod codec;
+#[cfg(feature = "forge")]
+pub mod forge;
+#[macro_use]
+pub mod packet_macro;
 pub mod packet_decoder;
 pub mod packet_encoder;
 #[cfg(feature = "query")]
@@ -17,10 +22,46 @@ pub mod query;
 #[cfg(feature = "serverbound")]
 pub mod server;
 
-/// The current Minecraft protocol number.
+/// The current (latest) Minecraft protocol number.
 /// Don't forget to change this when porting.
 pub const CURRENT_MC_PROTOCOL: NonZeroU16 = unsafe { NonZeroU16::new_unchecked(769) };
 
+/// Every protocol version a single server binary can speak, newest first.
+/// A handshake's requested protocol is matched against this table and, when
+/// found, becomes the per-connection protocol threaded into encode/decode.
+///
+/// NOTE: this is inert plumbing today. The table carries only the single
+/// current protocol and no handshake handler in this crate yet calls
+/// [`negotiate_protocol`] or feeds the matched version into
+/// [`StatusResponse::version`]'s `protocol`. Real multi-version support lands
+/// once a handshake handler populates this table with the additional builds
+/// and version-branches the packet codecs via the threaded
+/// `protocol_version`. Do not mistake the groundwork for working multi-version
+/// support.
+pub const SUPPORTED_PROTOCOLS: &[u16] = &[CURRENT_MC_PROTOCOL.get()];
+
+/// Returned by [`negotiate_protocol`] when the requested version is not in
+/// [`SUPPORTED_PROTOCOLS`].
+pub struct UnsupportedProtocol(pub u16);
+
+/// Returns `true` if `protocol` is in [`SUPPORTED_PROTOCOLS`].
+pub fn is_supported_protocol(protocol: u16) -> bool {
+    SUPPORTED_PROTOCOLS.contains(&protocol)
+}
+
+/// Validates a client's requested `protocol` against [`SUPPORTED_PROTOCOLS`],
+/// returning the matched version for the caller to store on its
+/// [`ConnectionProtocol`]. Call this when leaving
+/// [`ConnectionState::HandShake`]. Each connection keeps its own negotiated
+/// version, so concurrent clients on different versions never interfere.
+pub fn negotiate_protocol(protocol: u16) -> Result<u16, UnsupportedProtocol> {
+    if is_supported_protocol(protocol) {
+        Ok(protocol)
+    } else {
+        Err(UnsupportedProtocol(protocol))
+    }
+}
+
 pub const MAX_PACKET_SIZE: usize = 2097152;
 
 pub type FixedBitSet = bytes::Bytes;
@@ -65,6 +106,40 @@ impl TryFrom<VarInt> for ConnectionState {
     }
 }
 
+/// Per-connection protocol context: the current [`ConnectionState`] phase plus
+/// the protocol version negotiated during the handshake. Each connection owns
+/// one of these, so two concurrent clients on different versions never clobber
+/// each other's negotiated protocol (unlike a process-global would).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConnectionProtocol {
+    pub state: ConnectionState,
+    pub protocol_version: u16,
+}
+
+impl ConnectionProtocol {
+    /// A fresh context in [`ConnectionState::HandShake`], defaulting to
+    /// [`CURRENT_MC_PROTOCOL`] until the handshake negotiates a version.
+    pub fn new() -> Self {
+        Self {
+            state: ConnectionState::HandShake,
+            protocol_version: CURRENT_MC_PROTOCOL.get(),
+        }
+    }
+
+    /// Records the handshake-negotiated `protocol` on this connection after
+    /// validating it against [`SUPPORTED_PROTOCOLS`].
+    pub fn set_protocol(&mut self, protocol: u16) -> Result<(), UnsupportedProtocol> {
+        self.protocol_version = negotiate_protocol(protocol)?;
+        Ok(())
+    }
+}
+
+impl Default for ConnectionProtocol {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Deserialize, Clone)]
 pub struct IDOrSoundEvent {
     pub id: VarInt,
@@ -102,14 +177,100 @@ pub struct RawPacket {
     pub bytebuf: Bytes,
 }
 
-// TODO: Have the input be `impl Write`
 pub trait ClientPacket: Packet {
-    fn write(&self, bytebuf: &mut impl BufMut);
+    /// Serializes this packet for the peer speaking `protocol_version`, so a
+    /// single binary can encode for multiple client versions.
+    fn write(&self, protocol_version: u16, write: &mut impl Write);
 }
 
-// TODO: Have the input be `impl Read`
 pub trait ServerPacket: Packet + Sized {
-    fn read(bytebuf: &mut impl Buf) -> Result<Self, ReadingError>;
+    /// Deserializes a packet sent by a peer speaking `protocol_version`.
+    fn read(protocol_version: u16, read: &mut impl Read) -> Result<Self, ReadingError>;
+}
+
+/// A reader that refuses to hand out more than [`MAX_PACKET_SIZE`] bytes (or
+/// the budget given to [`LimitedRead::with_limit`]) over its lifetime, so a hostile length-prefixed collection can't force a
+/// gigabyte allocation before the decode fails.
+pub struct LimitedRead<R> {
+    inner: R,
+    remaining: usize,
+}
+
+impl<R: Read> LimitedRead<R> {
+    /// Wraps `inner`, allowing at most [`MAX_PACKET_SIZE`] bytes to be read.
+    pub fn new(inner: R) -> Self {
+        Self { inner, remaining: MAX_PACKET_SIZE }
+    }
+
+    /// Wraps `inner` with a custom byte budget.
+    pub fn with_limit(inner: R, limit: usize) -> Self {
+        Self { inner, remaining: limit }
+    }
+
+    /// Bytes still permitted before the reader errors.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl<R: Read> Read for LimitedRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            // The budget is spent. A packet of exactly the budget size is
+            // in-bounds, so a genuine EOF here is fine; only error if the
+            // inner reader actually has more bytes to offer (an over-limit
+            // packet). Probe a single byte to distinguish the two.
+            let mut probe = [0u8; 1];
+            return match self.inner.read(&mut probe)? {
+                0 => Ok(0),
+                _ => Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "packet exceeds MAX_PACKET_SIZE",
+                )),
+            };
+        }
+        let cap = buf.len().min(self.remaining);
+        let read = self.inner.read(&mut buf[..cap])?;
+        self.remaining -= read;
+        Ok(read)
+    }
+}
+
+/// Reads a `VarInt`-length-prefixed `Vec<T>`, decoding each element with `f`.
+///
+/// The length is validated against the reader's remaining byte budget before
+/// allocating, so an oversized prefix fails fast instead of reserving
+/// gigabytes up front.
+pub fn read_length_prefixed_vec<R, T>(
+    read: &mut LimitedRead<R>,
+    mut f: impl FnMut(&mut LimitedRead<R>) -> Result<T, ReadingError>,
+) -> Result<Vec<T>, ReadingError>
+where
+    R: Read,
+{
+    let len = <VarInt as packet_macro::PacketField>::read_field(read)?.0 as usize;
+    if len > read.remaining() {
+        return Err(ReadingError::TooLarge(
+            "length-prefixed collection exceeds remaining packet budget".to_string(),
+        ));
+    }
+    let mut out = Vec::with_capacity(len);
+    for _ in 0..len {
+        out.push(f(read)?);
+    }
+    Ok(out)
+}
+
+/// Reads the remainder of the packet into a byte buffer, bounded by the
+/// reader's [`MAX_PACKET_SIZE`] budget.
+pub fn read_to_end<R: Read>(read: &mut LimitedRead<R>) -> Result<Vec<u8>, ReadingError> {
+    let mut out = Vec::new();
+    read.read_to_end(&mut out)
+        .map_err(|e| ReadingError::Incomplete(e.to_string()))?;
+    Ok(out)
 }
 
 #[derive(Serialize)]
@@ -124,12 +285,18 @@ pub struct StatusResponse {
     pub favicon: Option<String>,
     /// Whether players are forced to use secure chat.
     pub enforce_secure_chat: bool,
+    /// The Forge mod list advertised to modded clients. (Optional)
+    #[cfg(feature = "forge")]
+    #[serde(rename = "modinfo", skip_serializing_if = "Option::is_none")]
+    pub forge_mods: Option<forge::ForgeStatus>,
 }
 #[derive(Serialize)]
 pub struct Version {
     /// The name of the version (e.g. 1.21.4)
     pub name: String,
-    /// The protocol version (e.g. 767)
+    /// The protocol version negotiated with the client (the connection's
+    /// [`ConnectionProtocol::protocol_version`]), or [`CURRENT_MC_PROTOCOL`]
+    /// before a match.
     pub protocol: u32,
 }
 
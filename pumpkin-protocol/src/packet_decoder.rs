@@ -0,0 +1,66 @@
+use std::io::Read;
+
+use bytes::{Buf, Bytes};
+use flate2::read::ZlibDecoder;
+
+use crate::bytebuf::ReadingError;
+use crate::codec::var_int::VarInt;
+use crate::packet_macro::PacketField;
+use crate::{CompressionThreshold, MAX_PACKET_SIZE};
+
+/// Decodes frames from the wire, optionally reversing the post-login
+/// compression stage once [`PacketDecoder::set_compression`] is called.
+#[derive(Default)]
+pub struct PacketDecoder {
+    compression: Option<CompressionThreshold>,
+}
+
+impl PacketDecoder {
+    pub fn new() -> Self {
+        Self { compression: None }
+    }
+
+    /// Enables the compression stage mid-connection, after `Set Compression`
+    /// has been received.
+    pub fn set_compression(&mut self, threshold: CompressionThreshold) {
+        self.compression = Some(threshold);
+    }
+
+    /// Decodes the body of a single frame, reversing compression when enabled.
+    ///
+    /// When compression is active the frame begins with a VarInt
+    /// uncompressed-data-length: `0` marks an uncompressed body, any other
+    /// value is the uncompressed size of the zlib-compressed remainder and is
+    /// rejected if it exceeds [`MAX_PACKET_SIZE`].
+    pub fn decode(&self, frame: Bytes) -> Result<Bytes, ReadingError> {
+        if self.compression.is_none() {
+            return Ok(frame);
+        }
+
+        // Read the uncompressed-data-length VarInt over the same `impl Read`
+        // abstraction the rest of the decode path uses.
+        let mut reader = frame.reader();
+        let data_len = VarInt::read_field(&mut reader)?.0;
+        if data_len == 0 {
+            // Uncompressed body: the remainder of the reader is the packet.
+            return Ok(Bytes::from(crate::read_to_end(&mut crate::LimitedRead::new(
+                reader,
+            ))?));
+        }
+
+        let data_len = data_len as usize;
+        if data_len > MAX_PACKET_SIZE {
+            return Err(ReadingError::TooLarge(
+                "declared uncompressed length exceeds MAX_PACKET_SIZE".to_string(),
+            ));
+        }
+
+        let mut decoder = ZlibDecoder::new(reader);
+        let mut out = Vec::with_capacity(data_len);
+        decoder
+            .take(data_len as u64)
+            .read_to_end(&mut out)
+            .map_err(|e| ReadingError::Incomplete(e.to_string()))?;
+        Ok(Bytes::from(out))
+    }
+}
@@ -0,0 +1,67 @@
+use std::io::Write;
+
+use bytes::{BufMut, BytesMut};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+
+use crate::bytebuf::ByteBufMut;
+use crate::codec::var_int::VarInt;
+use crate::{CompressionLevel, CompressionThreshold};
+
+/// Per-connection compression configuration, mirroring the `Set Compression`
+/// packet: bodies at or above [`CompressionThreshold`] are zlib-compressed at
+/// [`CompressionLevel`].
+#[derive(Debug, Clone, Copy)]
+pub struct CompressionInfo {
+    pub threshold: CompressionThreshold,
+    pub level: CompressionLevel,
+}
+
+/// Encodes frames onto the wire, optionally applying the post-login
+/// compression stage once [`PacketEncoder::set_compression`] is called.
+#[derive(Default)]
+pub struct PacketEncoder {
+    compression: Option<CompressionInfo>,
+}
+
+impl PacketEncoder {
+    pub fn new() -> Self {
+        Self { compression: None }
+    }
+
+    /// Enables the compression stage mid-connection, after `Set Compression`
+    /// has been sent.
+    pub fn set_compression(&mut self, info: CompressionInfo) {
+        self.compression = Some(info);
+    }
+
+    /// Encodes a single already-serialized packet body, returning the framed
+    /// bytes ready to write to the socket.
+    pub fn encode(&self, body: &[u8]) -> BytesMut {
+        let mut out = BytesMut::new();
+        match self.compression {
+            Some(info) if body.len() >= info.threshold.0 as usize => {
+                let mut encoder =
+                    ZlibEncoder::new(Vec::new(), Compression::new(info.level.0));
+                encoder
+                    .write_all(body)
+                    .expect("zlib compression into a Vec is infallible");
+                let compressed = encoder
+                    .finish()
+                    .expect("zlib compression into a Vec is infallible");
+                // Data-length prefix = the uncompressed size.
+                out.put_var_int(&VarInt(body.len() as i32));
+                out.put_slice(&compressed);
+            }
+            Some(_) => {
+                // Below the threshold: a zero data-length means "uncompressed".
+                out.put_var_int(&VarInt(0));
+                out.put_slice(body);
+            }
+            None => {
+                out.put_slice(body);
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,311 @@
+//! The [`state_packets!`] macro: generate packet structs, their [`Packet`]
+//! impls and the `id`-to-struct read dispatch from a single declaration.
+//!
+//! Each field may carry a `when(expr)` guard evaluated over the fields
+//! declared before it, so a field is only written/read when its condition
+//! holds. This expresses fields that depend on a prior boolean/enum, e.g.:
+//!
+//! ```ignore
+//! state_packets!(play {
+//!     clientbound {
+//!         0x16 => SetEntityMotion {
+//!             entity_id: VarInt,
+//!             has_velocity: bool,
+//!             velocity_x: i16 when (has_velocity),
+//!         }
+//!     }
+//! });
+//! ```
+//!
+//! Field types are single identifiers (`VarInt`, `i16`, `String`, ...). The
+//! generated `write`/`read` functions' `protocol_version` parameter is not in
+//! scope inside a guard, so guards cannot branch on the protocol version.
+//!
+//! [`Packet`]: crate::bytebuf::packet::Packet
+
+use std::io::{Read, Write};
+
+use crate::bytebuf::ReadingError;
+use crate::codec::identifier::Identifier;
+use crate::codec::var_int::VarInt;
+use crate::MAX_PACKET_SIZE;
+
+/// A single packet field that can be streamed to/from the socket with the
+/// crate's wire encoding. The [`state_packets!`] macro drives every field
+/// through this trait, so adding a new field type is a matter of implementing
+/// it here rather than hand-writing serialization.
+pub trait PacketField: Sized {
+    fn write_field(&self, write: &mut impl Write);
+    fn read_field(read: &mut impl Read) -> Result<Self, ReadingError>;
+}
+
+/// Reads exactly `buf.len()` bytes, mapping a short read to [`ReadingError`].
+fn read_exact(read: &mut impl Read, buf: &mut [u8]) -> Result<(), ReadingError> {
+    read.read_exact(buf)
+        .map_err(|e| ReadingError::Incomplete(e.to_string()))
+}
+
+macro_rules! impl_packet_field_be {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl PacketField for $ty {
+                fn write_field(&self, write: &mut impl Write) {
+                    write
+                        .write_all(&self.to_be_bytes())
+                        .expect("writing a fixed-width integer is infallible");
+                }
+
+                fn read_field(read: &mut impl Read) -> Result<Self, ReadingError> {
+                    let mut buf = [0u8; ::core::mem::size_of::<$ty>()];
+                    read_exact(read, &mut buf)?;
+                    Ok(<$ty>::from_be_bytes(buf))
+                }
+            }
+        )*
+    };
+}
+
+impl_packet_field_be!(i8, u8, i16, u16, i32, u32, i64, u64, f32, f64);
+
+impl PacketField for bool {
+    fn write_field(&self, write: &mut impl Write) {
+        (*self as u8).write_field(write);
+    }
+
+    fn read_field(read: &mut impl Read) -> Result<Self, ReadingError> {
+        Ok(u8::read_field(read)? != 0)
+    }
+}
+
+impl PacketField for VarInt {
+    fn write_field(&self, write: &mut impl Write) {
+        let mut value = self.0 as u32;
+        loop {
+            let mut byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value != 0 {
+                byte |= 0x80;
+            }
+            write
+                .write_all(&[byte])
+                .expect("writing a VarInt byte is infallible");
+            if value == 0 {
+                break;
+            }
+        }
+    }
+
+    fn read_field(read: &mut impl Read) -> Result<Self, ReadingError> {
+        let mut value: u32 = 0;
+        for shift in (0..5).map(|i| i * 7) {
+            let byte = u8::read_field(read)?;
+            value |= ((byte & 0x7F) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(VarInt(value as i32));
+            }
+        }
+        Err(ReadingError::TooLarge("VarInt".to_string()))
+    }
+}
+
+impl PacketField for String {
+    fn write_field(&self, write: &mut impl Write) {
+        VarInt(self.len() as i32).write_field(write);
+        write
+            .write_all(self.as_bytes())
+            .expect("writing string bytes is infallible");
+    }
+
+    fn read_field(read: &mut impl Read) -> Result<Self, ReadingError> {
+        let len = VarInt::read_field(read)?.0 as usize;
+        if len > MAX_PACKET_SIZE {
+            return Err(ReadingError::TooLarge("string length".to_string()));
+        }
+        let mut buf = vec![0u8; len];
+        read_exact(read, &mut buf)?;
+        String::from_utf8(buf).map_err(|e| ReadingError::Message(e.to_string()))
+    }
+}
+
+impl PacketField for Identifier {
+    fn write_field(&self, write: &mut impl Write) {
+        // An identifier rides the wire as its `namespace:path` string form.
+        self.to_string().write_field(write);
+    }
+
+    fn read_field(read: &mut impl Read) -> Result<Self, ReadingError> {
+        let raw = String::read_field(read)?;
+        raw.parse()
+            .map_err(|_| ReadingError::Message(format!("invalid identifier {raw:?}")))
+    }
+}
+
+/// Generates packet structs, their [`Packet`](crate::bytebuf::packet::Packet)
+/// impls and a per-direction dispatcher, namespaced into one module per
+/// state/direction so a single invocation can cover every state and both
+/// directions without name collisions.
+///
+/// A `Clientbound` block gets only [`ClientPacket`](crate::ClientPacket)
+/// (`write`); a `Serverbound` block gets only
+/// [`ServerPacket`](crate::ServerPacket) (`read`) plus a
+/// `read_packet(protocol_version, id, read)` dispatcher. Declare everything in
+/// a single invocation — each `State::direction` pair expands to its own
+/// module (`handshake::serverbound`, `status::clientbound`, …):
+///
+/// ```ignore
+/// state_packets!(
+///     handshake {
+///         serverbound {
+///             0x00 => Handshake { protocol_version: VarInt, address: String }
+///         }
+///     }
+///     status {
+///         clientbound { 0x00 => StatusResponse { json: String } }
+///         serverbound { 0x00 => StatusRequest {} }
+///     }
+/// );
+/// ```
+#[macro_export]
+macro_rules! state_packets {
+    (
+        $(
+            $state:ident {
+                $(
+                    $direction:ident {
+                        $(
+                            $id:literal => $name:ident {
+                                $(
+                                    $field:ident : $ty:ident $(when ($cond:expr))?
+                                ),* $(,)?
+                            }
+                        )*
+                    }
+                )*
+            }
+        )*
+    ) => {
+        $(
+            pub mod $state {
+                #[allow(unused_imports)]
+                use super::*;
+                $(
+                    pub mod $direction {
+                        #[allow(unused_imports)]
+                        use super::super::*;
+                        $(
+                            pub struct $name {
+                                $(pub $field: $ty,)*
+                            }
+
+                            impl $crate::bytebuf::packet::Packet for $name {
+                                const PACKET_ID: i32 = $id;
+                            }
+
+                            $crate::__state_packets_impl!(
+                                $direction, $name,
+                                $( $field : $ty $(when ($cond))? ),*
+                            );
+                        )*
+
+                        $crate::__state_packets_dispatch!(
+                            $direction, $( $id => $name )*
+                        );
+                    }
+                )*
+            }
+        )*
+    };
+}
+
+/// Emits the direction-appropriate packet impl: `ClientPacket` (write) for a
+/// `clientbound` block, `ServerPacket` (read) for a `serverbound` block.
+/// Internal to [`state_packets!`].
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __state_packets_impl {
+    (clientbound, $name:ident, $( $field:ident : $ty:ident $(when ($cond:expr))? ),*) => {
+        impl $crate::ClientPacket for $name {
+            #[allow(unused_variables)]
+            fn write(&self, protocol_version: u16, write: &mut impl ::std::io::Write) {
+                // Clone each field into an owned local so a `when` guard sees
+                // the same owned type on the write side as the read side does
+                // (e.g. `when(has_velocity)` is `bool`, not `&bool`).
+                $(let $field: $ty = ::core::clone::Clone::clone(&self.$field);)*
+                $(
+                    $crate::__state_packets_write!(write, $field $(, $cond)?);
+                )*
+            }
+        }
+    };
+    (serverbound, $name:ident, $( $field:ident : $ty:ident $(when ($cond:expr))? ),*) => {
+        impl $crate::ServerPacket for $name {
+            #[allow(unused_variables)]
+            fn read(
+                protocol_version: u16,
+                read: &mut impl ::std::io::Read,
+            ) -> Result<Self, $crate::bytebuf::ReadingError> {
+                $(
+                    let $field: $ty = $crate::__state_packets_read!(read, $ty $(, $cond)?);
+                )*
+                Ok(Self { $($field,)* })
+            }
+        }
+    };
+}
+
+/// Emits a `read_packet` dispatcher for a `serverbound` block only; a
+/// `clientbound` block has nothing to read. Internal to [`state_packets!`].
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __state_packets_dispatch {
+    (clientbound, $( $id:literal => $name:ident )*) => {};
+    (serverbound, $( $id:literal => $name:ident )*) => {
+        /// Reads the packet identified by `id`, or `None` for an unknown id in
+        /// this state/direction.
+        pub fn read_packet(
+            protocol_version: u16,
+            id: i32,
+            read: &mut impl ::std::io::Read,
+        ) -> Option<Result<Box<dyn ::core::any::Any>, $crate::bytebuf::ReadingError>> {
+            match id {
+                $(
+                    $id => Some(<$name as $crate::ServerPacket>::read(protocol_version, read)
+                        .map(|p| Box::new(p) as Box<dyn ::core::any::Any>)),
+                )*
+                _ => None,
+            }
+        }
+    };
+}
+
+/// Writes one field through [`PacketField`], honoring an optional `when`
+/// guard. Internal to [`state_packets!`].
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __state_packets_write {
+    ($write:ident, $field:ident) => {
+        $crate::packet_macro::PacketField::write_field(&$field, $write)
+    };
+    ($write:ident, $field:ident, $cond:expr) => {
+        if $cond {
+            $crate::packet_macro::PacketField::write_field(&$field, $write);
+        }
+    };
+}
+
+/// Reads one field through [`PacketField`], honoring an optional `when` guard;
+/// a skipped field falls back to [`Default`]. Internal to [`state_packets!`].
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __state_packets_read {
+    ($read:ident, $ty:ty) => {
+        <$ty as $crate::packet_macro::PacketField>::read_field($read)?
+    };
+    ($read:ident, $ty:ty, $cond:expr) => {
+        if $cond {
+            <$ty as $crate::packet_macro::PacketField>::read_field($read)?
+        } else {
+            <$ty as ::core::default::Default>::default()
+        }
+    };
+}